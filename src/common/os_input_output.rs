@@ -1,20 +1,29 @@
-use ipmpsc::{Receiver as IpcReceiver, Sender as IpcSender, SharedRingBuffer};
+use ipmpsc::{Receiver as RingBufferReceiver, Sender as RingBufferSender, SharedRingBuffer};
 use nix::fcntl::{fcntl, FcntlArg, OFlag};
 use nix::pty::{forkpty, Winsize};
-use nix::sys::signal::{kill, Signal};
+use nix::sys::signal::{kill, killpg, SigSet, Signal};
+use nix::sys::signalfd::{SfdFlags, SignalFd};
 use nix::sys::termios;
-use nix::sys::wait::waitpid;
+use nix::sys::wait::{waitpid, WaitPidFlag, WaitStatus};
 use nix::unistd;
 use nix::unistd::{ForkResult, Pid};
+use serde::de::DeserializeOwned;
 use serde::Serialize;
+use std::collections::HashMap;
+use std::convert::TryFrom;
 use std::env;
 use std::io;
 use std::io::prelude::*;
-use std::marker::PhantomData;
+use std::net::TcpStream;
 use std::os::unix::io::RawFd;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::os::unix::process::CommandExt;
 use std::path::PathBuf;
 use std::process::{Child, Command};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc as std_mpsc;
 use std::sync::{Arc, Mutex};
+use std::thread;
 
 use crate::client::ClientInstruction;
 use crate::errors::ErrorContext;
@@ -24,6 +33,10 @@ use crate::utils::consts::ZELLIJ_IPC_PIPE;
 
 const IPC_BUFFER_SIZE: u32 = 8388608;
 
+/// Disambiguates concurrently-attaching clients' Unix-socket reply paths; see
+/// [`ClientOsInputOutput::connect_to_server`].
+static CLIENT_SOCKET_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
 fn into_raw_mode(pid: RawFd) {
     let mut tio = termios::tcgetattr(pid).expect("could not get terminal attribute");
     termios::cfmakeraw(&mut tio);
@@ -40,6 +53,10 @@ fn unset_raw_mode(pid: RawFd, orig_termios: termios::Termios) {
     };
 }
 
+/// Returns the size of the terminal associated with file descriptor `fd`, including the pixel
+/// dimensions reported by `TIOCGWINSZ` (`ws_xpixel`/`ws_ypixel`), from which `PositionAndSize`
+/// derives its per-cell pixel size. Terminal image protocols (sixel, the kitty graphics
+/// protocol) need this to size images correctly.
 pub fn get_terminal_size_using_fd(fd: RawFd) -> PositionAndSize {
     // TODO: do this with the nix ioctl
     use libc::ioctl;
@@ -56,7 +73,19 @@ pub fn get_terminal_size_using_fd(fd: RawFd) -> PositionAndSize {
     PositionAndSize::from(winsize)
 }
 
-pub fn set_terminal_size_using_fd(fd: RawFd, columns: u16, rows: u16) {
+/// Sets the size of the terminal associated with file descriptor `fd`, in both character cells
+/// (`columns`/`rows`) and pixels (`width_px`/`height_px`).
+///
+/// Reporting the pixel dimensions lets programs running inside the pane (e.g. ones using the
+/// sixel or kitty graphics protocols) compute a per-cell pixel size via `TIOCGWINSZ`, which they
+/// need to scale images correctly.
+pub fn set_terminal_size_using_fd(
+    fd: RawFd,
+    columns: u16,
+    rows: u16,
+    width_px: u16,
+    height_px: u16,
+) {
     // TODO: do this with the nix ioctl
     use libc::ioctl;
     use libc::TIOCSWINSZ;
@@ -64,44 +93,109 @@ pub fn set_terminal_size_using_fd(fd: RawFd, columns: u16, rows: u16) {
     let winsize = Winsize {
         ws_col: columns,
         ws_row: rows,
-        ws_xpixel: 0,
-        ws_ypixel: 0,
+        ws_xpixel: width_px,
+        ws_ypixel: height_px,
     };
     unsafe { ioctl(fd, TIOCSWINSZ, &winsize) };
 }
 
-/// Handle some signals for the child process. This will loop until the child
-/// process exits.
+/// Waits for the child process to exit, forwarding job-control and termination signals to it in
+/// the meantime.
+///
+/// Rather than polling `child.try_wait()` on a timer, this blocks on a `signalfd` registered for
+/// `SIGCHLD` (real exit, reaped via a non-blocking `waitpid`) and
+/// `SIGWINCH`/`SIGINT`/`SIGTERM`/`SIGTSTP`/`SIGCONT` (forwarded to the child's process group via
+/// `killpg`, mirroring how a shell forwards job-control and resize signals to its foreground
+/// job). Blocking all of these here, rather than leaving them at their default disposition, also
+/// keeps `SIGINT` (Ctrl-C) from killing this supervisor itself instead of just the foreground
+/// job. This removes both the polling latency and the wasted wakeups, and makes
+/// Ctrl-Z/Ctrl-C/resize behave like they would in a native shell.
 fn handle_command_exit(mut child: Child) {
-    // register the SIGINT signal (TODO handle more signals)
-    let mut signals = ::signal_hook::iterator::Signals::new(&[SIGINT]).unwrap();
+    let child_pid = Pid::from_raw(child.id() as i32);
+
+    let mut mask = SigSet::empty();
+    mask.add(Signal::SIGCHLD);
+    mask.add(Signal::SIGWINCH);
+    mask.add(Signal::SIGINT);
+    mask.add(Signal::SIGTERM);
+    mask.add(Signal::SIGTSTP);
+    mask.add(Signal::SIGCONT);
+    mask.thread_block().expect("failed to block signals");
+    let signal_fd =
+        SignalFd::with_flags(&mask, SfdFlags::SFD_CLOEXEC).expect("failed to create signalfd");
+
     'handle_exit: loop {
-        // test whether the child process has exited
-        match child.try_wait() {
-            Ok(Some(_status)) => {
-                // if the child process has exited, break outside of the loop
-                // and exit this function
-                // TODO: handle errors?
-                break 'handle_exit;
-            }
-            Ok(None) => {
-                ::std::thread::sleep(::std::time::Duration::from_millis(100));
-            }
-            Err(e) => panic!("error attempting to wait: {}", e),
+        match signal_fd.read_signal() {
+            Ok(Some(siginfo)) => match Signal::try_from(siginfo.ssi_signo as i32) {
+                Ok(Signal::SIGCHLD) => match waitpid(child_pid, Some(WaitPidFlag::WNOHANG)) {
+                    Ok(WaitStatus::Exited(..)) | Ok(WaitStatus::Signaled(..)) => {
+                        break 'handle_exit;
+                    }
+                    Ok(_) => {}
+                    Err(e) => panic!("error attempting to wait: {}", e),
+                },
+                Ok(signal @ Signal::SIGWINCH)
+                | Ok(signal @ Signal::SIGINT)
+                | Ok(signal @ Signal::SIGTERM)
+                | Ok(signal @ Signal::SIGTSTP)
+                | Ok(signal @ Signal::SIGCONT) => {
+                    // Forward to the child's process group: all of these are in our blocked set,
+                    // so this supervisor never acts on them itself.
+                    killpg(child_pid, signal).ok();
+                }
+                _ => {}
+            },
+            Ok(None) => {}
+            Err(e) => panic!("error reading signalfd: {}", e),
         }
+    }
+    child.wait().ok();
+}
 
-        for signal in signals.pending() {
-            if let SIGINT = signal {
-                child.kill().unwrap();
-                child.wait().unwrap();
-                break 'handle_exit;
-            }
-        }
+/// Configuration for the process spawned by [`spawn_terminal`].
+///
+/// This mirrors what a real terminal emulator sets up for the shell it launches: a working
+/// directory, a handful of environment overrides layered on top of the inherited environment,
+/// and whether the shell should be started as a login shell.
+#[derive(Clone, Debug, Default)]
+pub struct TerminalSpawnConfig {
+    /// Working directory for the spawned process. Defaults to the current process's cwd when
+    /// `None`.
+    pub cwd: Option<PathBuf>,
+    /// Environment variables to set (or override) in the spawned process, on top of the
+    /// inherited environment.
+    pub env: HashMap<String, String>,
+    /// Start the shell as a login shell (leading `-` on `argv[0]`, plus `-l`).
+    pub login_shell: bool,
+}
+
+/// Applies `spawn_config` to `command`: overrides environment variables, defaults `TERM` and
+/// `COLORTERM` if unset, and (for a login shell) passes `-l` and prefixes `argv[0]` with `-`, the
+/// same convention login shells use to tell themselves apart from interactive-only invocations.
+fn apply_spawn_config(command: &mut Command, program: &str, spawn_config: &TerminalSpawnConfig) {
+    command.env(
+        "TERM",
+        env::var("TERM").unwrap_or_else(|_| "xterm-256color".into()),
+    );
+    command.env(
+        "COLORTERM",
+        env::var("COLORTERM").unwrap_or_else(|_| "truecolor".into()),
+    );
+    command.envs(&spawn_config.env);
+
+    if spawn_config.login_shell {
+        let leader = PathBuf::from(program);
+        let name = leader
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or(program);
+        command.arg0(format!("-{}", name));
+        command.arg("-l");
     }
 }
 
 /// Spawns a new terminal from the parent terminal with [`termios`](termios::Termios)
-/// `orig_termios`.
+/// `orig_termios`, configured per `spawn_config`.
 ///
 /// If a `file_to_open` is given, the text editor specified by environment variable `EDITOR`
 /// (or `VISUAL`, if `EDITOR` is not set) will be started in the new terminal, with the given
@@ -114,7 +208,11 @@ fn handle_command_exit(mut child: Child) {
 /// set.
 // FIXME this should probably be split into different functions, or at least have less levels
 // of indentation in some way
-fn spawn_terminal(file_to_open: Option<PathBuf>, orig_termios: termios::Termios) -> (RawFd, RawFd) {
+fn spawn_terminal(
+    file_to_open: Option<PathBuf>,
+    orig_termios: termios::Termios,
+    spawn_config: TerminalSpawnConfig,
+) -> (RawFd, RawFd) {
     let (pid_primary, pid_secondary): (RawFd, RawFd) = {
         match forkpty(None, Some(&orig_termios)) {
             Ok(fork_pty_res) => {
@@ -126,29 +224,40 @@ fn spawn_terminal(file_to_open: Option<PathBuf>, orig_termios: termios::Termios)
                             .expect("could not fcntl");
                         child
                     }
-                    ForkResult::Child => match file_to_open {
-                        Some(file_to_open) => {
-                            if env::var("EDITOR").is_err() && env::var("VISUAL").is_err() {
-                                panic!("Can't edit files if an editor is not defined. To fix: define the EDITOR or VISUAL environment variables with the path to your editor (eg. /usr/bin/vim)");
-                            }
-                            let editor =
-                                env::var("EDITOR").unwrap_or_else(|_| env::var("VISUAL").unwrap());
-
-                            let child = Command::new(editor)
-                                .args(&[file_to_open])
-                                .spawn()
-                                .expect("failed to spawn");
-                            handle_command_exit(child);
-                            ::std::process::exit(0);
+                    ForkResult::Child => {
+                        // `forkpty` has already made the secondary side of the pty our
+                        // controlling terminal (the `login_tty`-equivalent session setup), so
+                        // job control (Ctrl-Z, Ctrl-C, `tty` detection) already works here; no
+                        // further `setsid`/`TIOCSCTTY` dance is needed.
+                        if let Some(cwd) = spawn_config.cwd.as_ref() {
+                            unistd::chdir(cwd).expect("failed to chdir into requested cwd");
                         }
-                        None => {
-                            let child = Command::new(env::var("SHELL").unwrap())
-                                .spawn()
-                                .expect("failed to spawn");
-                            handle_command_exit(child);
-                            ::std::process::exit(0);
+
+                        match file_to_open {
+                            Some(file_to_open) => {
+                                if env::var("EDITOR").is_err() && env::var("VISUAL").is_err() {
+                                    panic!("Can't edit files if an editor is not defined. To fix: define the EDITOR or VISUAL environment variables with the path to your editor (eg. /usr/bin/vim)");
+                                }
+                                let editor = env::var("EDITOR")
+                                    .unwrap_or_else(|_| env::var("VISUAL").unwrap());
+
+                                let mut cmd = Command::new(&editor);
+                                apply_spawn_config(&mut cmd, &editor, &spawn_config);
+                                let child =
+                                    cmd.args(&[file_to_open]).spawn().expect("failed to spawn");
+                                handle_command_exit(child);
+                                ::std::process::exit(0);
+                            }
+                            None => {
+                                let shell = env::var("SHELL").unwrap();
+                                let mut cmd = Command::new(&shell);
+                                apply_spawn_config(&mut cmd, &shell, &spawn_config);
+                                let child = cmd.spawn().expect("failed to spawn");
+                                handle_command_exit(child);
+                                ::std::process::exit(0);
+                            }
                         }
-                    },
+                    }
                 };
                 (pid_primary, pid_secondary.as_raw())
             }
@@ -160,21 +269,243 @@ fn spawn_terminal(file_to_open: Option<PathBuf>, orig_termios: termios::Termios)
     (pid_primary, pid_secondary)
 }
 
-/// Sends messages on an [ipmpsc](ipmpsc) channel, along with an [`ErrorContext`].
+/// A transport capable of sending serialized messages of type `T`, along with an
+/// [`ErrorContext`], to a matching [`IpcReceiver`] on the other end.
+///
+/// Implemented by the default [`RingBufferTransport`] (a locally shared
+/// [`SharedRingBuffer`](ipmpsc::SharedRingBuffer), which requires the client and server to share
+/// a filesystem) and by [`SocketTransport`] (a Unix-domain or TCP socket), so a session can be
+/// attached from another host or container instead of just the local machine.
+trait IpcSender<T>: Send {
+    fn send(&self, msg: T, err_ctx: ErrorContext) -> io::Result<()>;
+    /// Returns a [`Box`] pointer to this sender, so it can be stored behind `dyn IpcSender<T>`.
+    fn box_clone(&self) -> Box<dyn IpcSender<T>>;
+}
+
+/// A transport capable of receiving serialized messages of type `T`, along with an
+/// [`ErrorContext`], sent by a matching [`IpcSender`] on the other end. Implemented by the
+/// backends listed on [`IpcSender`], and by [`SocketListenerReceiver`], which receives from every
+/// client that connects to a listening socket.
+trait IpcReceiver<T>: Send {
+    fn recv(&mut self) -> io::Result<(T, ErrorContext)>;
+}
+
+fn ipc_err<E: std::fmt::Display>(e: E) -> io::Error {
+    io::Error::new(io::ErrorKind::Other, e.to_string())
+}
+
+/// The default IPC backend: a [`SharedRingBuffer`](ipmpsc::SharedRingBuffer) mapped from a local
+/// file. Cheap to send/receive on, but requires the client and server to share a filesystem.
+///
+/// The underlying [`RingBufferReceiver`] is created lazily, on the first `recv()`, and then kept
+/// around for the lifetime of this transport: it tracks its own read position, so rebuilding it
+/// on every call would re-read the same messages from the start.
 #[derive(Clone)]
-struct IpcSenderWithContext<T: Serialize> {
-    err_ctx: ErrorContext,
-    sender: IpcSender,
-    _phantom: PhantomData<T>,
+struct RingBufferTransport {
+    buffer: SharedRingBuffer,
+    receiver: Arc<Mutex<Option<RingBufferReceiver>>>,
 }
 
-impl<T: Serialize> IpcSenderWithContext<T> {
-    /// Returns a sender to the given [SharedRingBuffer](ipmpsc::SharedRingBuffer).
+impl RingBufferTransport {
     fn new(buffer: SharedRingBuffer) -> Self {
+        Self {
+            buffer,
+            receiver: Arc::new(Mutex::new(None)),
+        }
+    }
+}
+
+impl<T: Serialize + Send + 'static> IpcSender<T> for RingBufferTransport {
+    fn send(&self, msg: T, err_ctx: ErrorContext) -> io::Result<()> {
+        RingBufferSender::new(self.buffer.clone())
+            .send(&(msg, err_ctx))
+            .map_err(ipc_err)
+    }
+    fn box_clone(&self) -> Box<dyn IpcSender<T>> {
+        Box::new(self.clone())
+    }
+}
+
+impl<T: DeserializeOwned + Send> IpcReceiver<T> for RingBufferTransport {
+    fn recv(&mut self) -> io::Result<(T, ErrorContext)> {
+        let mut receiver = self.receiver.lock().unwrap();
+        receiver
+            .get_or_insert_with(|| RingBufferReceiver::new(self.buffer.clone()))
+            .recv()
+            .map_err(ipc_err)
+    }
+}
+
+/// A Unix-domain or TCP socket used as an IPC backend instead of a locally shared ring buffer,
+/// so a Zellij session can be attached from another machine or a container. Borrows the
+/// cross-process sender/receiver channel model from constellation: messages are length-prefixed,
+/// `bincode`-encoded frames written to (and read from) a plain duplex stream.
+enum SocketStream {
+    Unix(UnixStream),
+    Tcp(TcpStream),
+}
+
+impl SocketStream {
+    fn try_clone(&self) -> io::Result<Self> {
+        match self {
+            Self::Unix(stream) => stream.try_clone().map(Self::Unix),
+            Self::Tcp(stream) => stream.try_clone().map(Self::Tcp),
+        }
+    }
+
+    fn write_frame(&self, bytes: &[u8]) -> io::Result<()> {
+        let len = (bytes.len() as u32).to_le_bytes();
+        match self {
+            Self::Unix(stream) => {
+                (&*stream).write_all(&len)?;
+                (&*stream).write_all(bytes)
+            }
+            Self::Tcp(stream) => {
+                (&*stream).write_all(&len)?;
+                (&*stream).write_all(bytes)
+            }
+        }
+    }
+
+    fn read_frame(&self) -> io::Result<Vec<u8>> {
+        let mut len_buf = [0u8; 4];
+        match self {
+            Self::Unix(stream) => (&*stream).read_exact(&mut len_buf)?,
+            Self::Tcp(stream) => (&*stream).read_exact(&mut len_buf)?,
+        };
+        let len = u32::from_le_bytes(len_buf);
+        if len > IPC_BUFFER_SIZE {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "frame length {} exceeds the {} byte limit",
+                    len, IPC_BUFFER_SIZE
+                ),
+            ));
+        }
+
+        let mut buf = vec![0u8; len as usize];
+        match self {
+            Self::Unix(stream) => (&*stream).read_exact(&mut buf)?,
+            Self::Tcp(stream) => (&*stream).read_exact(&mut buf)?,
+        };
+        Ok(buf)
+    }
+}
+
+#[cfg(test)]
+mod socket_stream_tests {
+    use super::*;
+
+    #[test]
+    fn frame_round_trip() {
+        let (a, b) = UnixStream::pair().expect("failed to create socket pair");
+        let a = SocketStream::Unix(a);
+        let b = SocketStream::Unix(b);
+
+        a.write_frame(b"hello").expect("failed to write frame");
+        assert_eq!(b.read_frame().expect("failed to read frame"), b"hello");
+    }
+
+    #[test]
+    fn oversized_length_prefix_is_rejected_before_allocating() {
+        let (a, b) = UnixStream::pair().expect("failed to create socket pair");
+        let oversized_len = (IPC_BUFFER_SIZE + 1).to_le_bytes();
+        (&a).write_all(&oversized_len)
+            .expect("failed to write length prefix");
+
+        let b = SocketStream::Unix(b);
+        assert!(b.read_frame().is_err());
+    }
+}
+
+struct SocketTransport {
+    stream: SocketStream,
+}
+
+impl<T: Serialize + Send> IpcSender<T> for SocketTransport {
+    fn send(&self, msg: T, err_ctx: ErrorContext) -> io::Result<()> {
+        let bytes = bincode::serialize(&(msg, err_ctx)).map_err(ipc_err)?;
+        self.stream.write_frame(&bytes)
+    }
+    fn box_clone(&self) -> Box<dyn IpcSender<T>> {
+        let stream = self.stream.try_clone().expect("failed to clone socket");
+        Box::new(SocketTransport { stream })
+    }
+}
+
+impl<T: DeserializeOwned + Send> IpcReceiver<T> for SocketTransport {
+    fn recv(&mut self) -> io::Result<(T, ErrorContext)> {
+        let bytes = self.stream.read_frame()?;
+        bincode::deserialize(&bytes).map_err(ipc_err)
+    }
+}
+
+/// Identifies where to reach the other end of an IPC channel: a local shared-memory pipe (the
+/// historical default, requires a shared filesystem) or a socket address, so the client and
+/// server don't have to be on the same host.
+#[derive(Clone, Debug)]
+pub enum IpcConnection {
+    /// A [`SharedRingBuffer`](ipmpsc::SharedRingBuffer) at the given file path.
+    SharedRingBuffer(String),
+    /// A Unix-domain socket at the given path.
+    UnixSocket(PathBuf),
+    /// A TCP endpoint, e.g. for attaching from another host.
+    Tcp(std::net::SocketAddr),
+}
+
+impl Default for IpcConnection {
+    /// The historical default: a [`SharedRingBuffer`](ipmpsc::SharedRingBuffer) at
+    /// [`ZELLIJ_IPC_PIPE`].
+    fn default() -> Self {
+        IpcConnection::SharedRingBuffer(ZELLIJ_IPC_PIPE.to_string())
+    }
+}
+
+/// Sends messages on an [`IpcSender`] transport, along with an [`ErrorContext`].
+struct IpcSenderWithContext<T> {
+    err_ctx: ErrorContext,
+    sender: Box<dyn IpcSender<T>>,
+}
+
+impl<T> Clone for IpcSenderWithContext<T> {
+    fn clone(&self) -> Self {
+        Self {
+            err_ctx: self.err_ctx,
+            sender: self.sender.box_clone(),
+        }
+    }
+}
+
+impl<T: Serialize + Send + 'static> IpcSenderWithContext<T> {
+    /// Returns a sender for the given [`IpcConnection`], dialing or mapping whichever backend it
+    /// names.
+    fn new(connection: &IpcConnection) -> io::Result<Self> {
+        let sender: Box<dyn IpcSender<T>> = match connection {
+            IpcConnection::SharedRingBuffer(path) => {
+                let buffer = SharedRingBuffer::open(path).map_err(ipc_err)?;
+                Box::new(RingBufferTransport::new(buffer))
+            }
+            IpcConnection::UnixSocket(path) => Box::new(SocketTransport {
+                stream: SocketStream::Unix(UnixStream::connect(path)?),
+            }),
+            IpcConnection::Tcp(addr) => Box::new(SocketTransport {
+                stream: SocketStream::Tcp(TcpStream::connect(addr)?),
+            }),
+        };
+        Ok(Self {
+            err_ctx: ErrorContext::new(),
+            sender,
+        })
+    }
+
+    /// Wraps an already-established [`IpcSender`], e.g. the server's self-loopback sender
+    /// returned alongside its receiver by [`open_server_channel`], for sending messages to
+    /// oneself.
+    fn from_sender(sender: Box<dyn IpcSender<T>>) -> Self {
         Self {
             err_ctx: ErrorContext::new(),
-            sender: IpcSender::new(buffer),
-            _phantom: PhantomData,
+            sender,
         }
     }
 
@@ -189,8 +520,121 @@ impl<T: Serialize> IpcSenderWithContext<T> {
 
     /// Sends an event, along with the current [`ErrorContext`], on this
     /// [`IpcSenderWithContext`]'s channel.
-    fn send(&self, msg: T) -> ipmpsc::Result<()> {
-        self.sender.send(&(msg, self.err_ctx))
+    fn send(&self, msg: T) -> io::Result<()> {
+        self.sender.send(msg, self.err_ctx)
+    }
+}
+
+impl Clone for SocketTransport {
+    fn clone(&self) -> Self {
+        Self {
+            stream: self.stream.try_clone().expect("failed to clone socket"),
+        }
+    }
+}
+
+/// A listener for either socket backend, abstracted just enough to let
+/// [`SocketListenerReceiver`] accept on whichever one it was handed.
+enum Listener {
+    Unix(UnixListener),
+    Tcp(std::net::TcpListener),
+}
+
+impl Listener {
+    fn accept(&self) -> io::Result<SocketStream> {
+        match self {
+            Self::Unix(listener) => listener
+                .accept()
+                .map(|(stream, _)| SocketStream::Unix(stream)),
+            Self::Tcp(listener) => listener
+                .accept()
+                .map(|(stream, _)| SocketStream::Tcp(stream)),
+        }
+    }
+}
+
+/// The socket-backed counterpart to [`RingBufferTransport`]'s "any number of openers can write"
+/// model: a [`SharedRingBuffer`](ipmpsc::SharedRingBuffer) needs no accept step at all, since any
+/// process that opens the same file can send, but a listening socket only ever accepts one peer
+/// per `accept()` call. This runs an accept loop on a background thread, and a reader thread per
+/// accepted connection, funnelling every message any of them send into one channel so the server
+/// can keep receiving from new clients instead of being limited to the first one that connected.
+struct SocketListenerReceiver<T> {
+    inbox: std_mpsc::Receiver<io::Result<(T, ErrorContext)>>,
+}
+
+impl<T: DeserializeOwned + Send + 'static> SocketListenerReceiver<T> {
+    fn spawn(listener: Listener) -> Self {
+        let (tx, rx) = std_mpsc::channel();
+        thread::spawn(move || loop {
+            let stream = match listener.accept() {
+                Ok(stream) => stream,
+                Err(_) => return,
+            };
+            let tx = tx.clone();
+            thread::spawn(move || loop {
+                let msg = match stream.read_frame() {
+                    Ok(bytes) => bincode::deserialize(&bytes).map_err(ipc_err),
+                    Err(e) => Err(e),
+                };
+                let is_err = msg.is_err();
+                if tx.send(msg).is_err() || is_err {
+                    return;
+                }
+            });
+        });
+        Self { inbox: rx }
+    }
+}
+
+impl<T: Send> IpcReceiver<T> for SocketListenerReceiver<T> {
+    fn recv(&mut self) -> io::Result<(T, ErrorContext)> {
+        self.inbox
+            .recv()
+            .map_err(|e| io::Error::new(io::ErrorKind::BrokenPipe, e.to_string()))?
+    }
+}
+
+/// Sets up both ends of the given [`IpcConnection`] as seen from the listening/accepting side
+/// (the server): a sender it can use to talk to itself (e.g. the server's self-directed `Exit`
+/// message) and a receiver fed by every client that connects.
+///
+/// For [`IpcConnection::SharedRingBuffer`] this just creates the backing file and opens it twice
+/// (as sender and receiver), since any number of processes can map and write to the same ring
+/// buffer. For the socket backends, a [`SocketListenerReceiver`] accepts connections from any
+/// number of clients in the background, and the self-sender is a loopback connection to the
+/// same listening address.
+fn open_server_channel<T>(
+    connection: &IpcConnection,
+) -> io::Result<(Box<dyn IpcSender<T>>, Box<dyn IpcReceiver<T>>)>
+where
+    T: Serialize + DeserializeOwned + Send + 'static,
+{
+    match connection {
+        IpcConnection::SharedRingBuffer(path) => {
+            let buffer = SharedRingBuffer::create(path, IPC_BUFFER_SIZE).map_err(ipc_err)?;
+            let sender = RingBufferTransport::new(buffer.clone());
+            let receiver = RingBufferTransport::new(buffer);
+            Ok((Box::new(sender), Box::new(receiver)))
+        }
+        IpcConnection::UnixSocket(path) => {
+            let _ = std::fs::remove_file(path);
+            let listener = UnixListener::bind(path)?;
+            let receiver = SocketListenerReceiver::spawn(Listener::Unix(listener));
+            let sender = SocketTransport {
+                stream: SocketStream::Unix(UnixStream::connect(path)?),
+            };
+            Ok((Box::new(sender), Box::new(receiver)))
+        }
+        IpcConnection::Tcp(addr) => {
+            let listener = std::net::TcpListener::bind(addr)?;
+            let bound_addr = listener.local_addr()?;
+            let receiver = SocketListenerReceiver::spawn(Listener::Tcp(listener));
+            let sender = SocketTransport {
+                stream: SocketStream::Tcp(TcpStream::connect(bound_addr)?),
+            };
+            Ok((Box::new(sender), Box::new(receiver)))
+        }
     }
 }
 
@@ -198,17 +642,30 @@ impl<T: Serialize> IpcSenderWithContext<T> {
 pub struct ServerOsInputOutput {
     orig_termios: Arc<Mutex<termios::Termios>>,
     server_sender: IpcSenderWithContext<ServerInstruction>,
-    server_receiver: Arc<Mutex<IpcReceiver>>,
+    server_receiver: Arc<Mutex<Box<dyn IpcReceiver<ServerInstruction>>>>,
     client_sender: Option<IpcSenderWithContext<ClientInstruction>>,
 }
 
 /// The `ServerOsApi` trait represents an abstract interface to the features of an operating system that
 /// Zellij server requires.
 pub trait ServerOsApi: Send + Sync {
-    /// Sets the size of the terminal associated to file descriptor `fd`.
-    fn set_terminal_size_using_fd(&mut self, fd: RawFd, cols: u16, rows: u16);
-    /// Spawn a new terminal, with an optional file to open in a terminal program.
-    fn spawn_terminal(&mut self, file_to_open: Option<PathBuf>) -> (RawFd, RawFd);
+    /// Sets the size of the terminal associated to file descriptor `fd`, in character cells
+    /// (`cols`/`rows`) and pixels (`width_px`/`height_px`).
+    fn set_terminal_size_using_fd(
+        &mut self,
+        fd: RawFd,
+        cols: u16,
+        rows: u16,
+        width_px: u16,
+        height_px: u16,
+    );
+    /// Spawn a new terminal, with an optional file to open in a terminal program, configured per
+    /// `spawn_config` (working directory, environment overrides, login shell).
+    fn spawn_terminal(
+        &mut self,
+        file_to_open: Option<PathBuf>,
+        spawn_config: TerminalSpawnConfig,
+    ) -> (RawFd, RawFd);
     /// Read bytes from the standard output of the virtual terminal referred to by `fd`.
     fn read_from_tty_stdout(&mut self, fd: RawFd, buf: &mut [u8]) -> Result<usize, nix::Error>;
     /// Write bytes to the standard input of the virtual terminal referred to by `fd`.
@@ -228,19 +685,30 @@ pub trait ServerOsApi: Send + Sync {
     fn server_recv(&self) -> (ServerInstruction, ErrorContext);
     /// Sends a message to client
     fn send_to_client(&mut self, msg: ClientInstruction);
-    /// Adds a sender to client
-    fn add_client_sender(&mut self, buffer_path: String);
+    /// Adds a sender to client, dialing the given [`IpcConnection`]
+    fn add_client_sender(&mut self, connection: IpcConnection);
     /// Update ErrorContext of senders
     fn update_senders(&mut self, new_ctx: ErrorContext);
 }
 
 impl ServerOsApi for ServerOsInputOutput {
-    fn set_terminal_size_using_fd(&mut self, fd: RawFd, cols: u16, rows: u16) {
-        set_terminal_size_using_fd(fd, cols, rows);
+    fn set_terminal_size_using_fd(
+        &mut self,
+        fd: RawFd,
+        cols: u16,
+        rows: u16,
+        width_px: u16,
+        height_px: u16,
+    ) {
+        set_terminal_size_using_fd(fd, cols, rows, width_px, height_px);
     }
-    fn spawn_terminal(&mut self, file_to_open: Option<PathBuf>) -> (RawFd, RawFd) {
+    fn spawn_terminal(
+        &mut self,
+        file_to_open: Option<PathBuf>,
+        spawn_config: TerminalSpawnConfig,
+    ) -> (RawFd, RawFd) {
         let orig_termios = self.orig_termios.lock().unwrap();
-        spawn_terminal(file_to_open, orig_termios.clone())
+        spawn_terminal(file_to_open, orig_termios.clone(), spawn_config)
     }
     fn read_from_tty_stdout(&mut self, fd: RawFd, buf: &mut [u8]) -> Result<usize, nix::Error> {
         unistd::read(fd, buf)
@@ -268,9 +736,9 @@ impl ServerOsApi for ServerOsInputOutput {
     fn send_to_client(&mut self, msg: ClientInstruction) {
         self.client_sender.as_mut().unwrap().send(msg).unwrap();
     }
-    fn add_client_sender(&mut self, buffer_path: String) {
-        let buffer = SharedRingBuffer::open(buffer_path.as_str()).unwrap();
-        self.client_sender = Some(IpcSenderWithContext::new(buffer));
+    fn add_client_sender(&mut self, connection: IpcConnection) {
+        self.client_sender =
+            Some(IpcSenderWithContext::new(&connection).expect("failed to connect to client"));
     }
     fn update_senders(&mut self, new_ctx: ErrorContext) {
         self.server_sender.update(new_ctx);
@@ -286,12 +754,14 @@ impl Clone for Box<dyn ServerOsApi> {
     }
 }
 
-pub fn get_server_os_input() -> ServerOsInputOutput {
+pub fn get_server_os_input(connection: IpcConnection) -> ServerOsInputOutput {
     let current_termios = termios::tcgetattr(0).unwrap();
     let orig_termios = Arc::new(Mutex::new(current_termios));
-    let server_buffer = SharedRingBuffer::create(ZELLIJ_IPC_PIPE, IPC_BUFFER_SIZE).unwrap();
-    let server_sender = IpcSenderWithContext::new(server_buffer.clone());
-    let server_receiver = Arc::new(Mutex::new(IpcReceiver::new(server_buffer.clone())));
+    let (sender, receiver) =
+        open_server_channel(&connection).expect("failed to set up server IPC channel");
+    let server_sender = IpcSenderWithContext::from_sender(sender);
+    let server_receiver: Arc<Mutex<Box<dyn IpcReceiver<ServerInstruction>>>> =
+        Arc::new(Mutex::new(receiver));
     ServerOsInputOutput {
         orig_termios,
         server_sender,
@@ -305,7 +775,9 @@ pub struct ClientOsInputOutput {
     orig_termios: Arc<Mutex<termios::Termios>>,
     server_sender: IpcSenderWithContext<ServerInstruction>,
     // This is used by router thread only hence lock resolves immediately.
-    client_receiver: Option<Arc<Mutex<IpcReceiver>>>,
+    client_receiver: Option<Arc<Mutex<Box<dyn IpcReceiver<ClientInstruction>>>>>,
+    // The backend to listen on for the server's reply channel, set up in `connect_to_server`.
+    connection: IpcConnection,
 }
 
 /// The `ClientOsApi` trait represents an abstract interface to the features of an operating system that
@@ -370,15 +842,85 @@ impl ClientOsApi for ClientOsInputOutput {
         self.server_sender.update(new_ctx);
     }
     fn connect_to_server(&mut self, full_screen_ws: PositionAndSize) {
-        let (client_buffer_path, client_buffer) =
-            SharedRingBuffer::create_temp(IPC_BUFFER_SIZE).unwrap();
-        self.client_receiver = Some(Arc::new(Mutex::new(IpcReceiver::new(
-            client_buffer.clone(),
-        ))));
+        // Set up our side of the reply channel the server will use to send us
+        // `ClientInstruction`s, using whichever backend `self.connection` names, then tell the
+        // server where to find it. For the socket backends this means listening first and
+        // accepting only after the server has been told to dial us, to avoid both ends blocking
+        // on each other.
+        enum PendingListener {
+            RingBuffer(SharedRingBuffer),
+            Unix(UnixListener),
+            Tcp(std::net::TcpListener),
+        }
+
+        let (listen_connection, pending) = match &self.connection {
+            IpcConnection::SharedRingBuffer(_) => {
+                let (path, buffer) = SharedRingBuffer::create_temp(IPC_BUFFER_SIZE).unwrap();
+                (
+                    IpcConnection::SharedRingBuffer(path),
+                    PendingListener::RingBuffer(buffer),
+                )
+            }
+            IpcConnection::UnixSocket(path) => {
+                // Unique per client (pid + a monotonic counter), so two clients attaching
+                // concurrently don't race to bind (and unlink) the same reply socket.
+                let client_id = CLIENT_SOCKET_COUNTER.fetch_add(1, Ordering::Relaxed);
+                let listen_path =
+                    path.with_extension(format!("{}-{}.client", std::process::id(), client_id));
+                let _ = std::fs::remove_file(&listen_path);
+                let listener =
+                    UnixListener::bind(&listen_path).expect("failed to bind client listen socket");
+                (
+                    IpcConnection::UnixSocket(listen_path),
+                    PendingListener::Unix(listener),
+                )
+            }
+            IpcConnection::Tcp(server_addr) => {
+                // Listen on the local address that routes to the server rather than on loopback,
+                // so a server on another host can dial back. Connecting a UDP socket sends
+                // nothing; it only picks the outgoing interface.
+                let unspecified: std::net::IpAddr = if server_addr.is_ipv4() {
+                    std::net::Ipv4Addr::UNSPECIFIED.into()
+                } else {
+                    std::net::Ipv6Addr::UNSPECIFIED.into()
+                };
+                let probe = std::net::UdpSocket::bind((unspecified, 0))
+                    .expect("failed to bind route probe socket");
+                probe
+                    .connect(server_addr)
+                    .expect("failed to find a route to the server");
+                let local_ip = probe.local_addr().unwrap().ip();
+                let listener = std::net::TcpListener::bind((local_ip, 0))
+                    .expect("failed to bind client listen socket");
+                let listen_addr = listener.local_addr().unwrap();
+                (
+                    IpcConnection::Tcp(listen_addr),
+                    PendingListener::Tcp(listener),
+                )
+            }
+        };
+
         self.send_to_server(ServerInstruction::NewClient(
-            client_buffer_path,
+            listen_connection,
             full_screen_ws,
         ));
+
+        let receiver: Box<dyn IpcReceiver<ClientInstruction>> = match pending {
+            PendingListener::RingBuffer(buffer) => Box::new(RingBufferTransport::new(buffer)),
+            PendingListener::Unix(listener) => {
+                let (stream, _) = listener.accept().expect("server never connected back");
+                Box::new(SocketTransport {
+                    stream: SocketStream::Unix(stream),
+                })
+            }
+            PendingListener::Tcp(listener) => {
+                let (stream, _) = listener.accept().expect("server never connected back");
+                Box::new(SocketTransport {
+                    stream: SocketStream::Tcp(stream),
+                })
+            }
+        };
+        self.client_receiver = Some(Arc::new(Mutex::new(receiver)));
     }
     fn client_recv(&self) -> (ClientInstruction, ErrorContext) {
         self.client_receiver
@@ -397,14 +939,15 @@ impl Clone for Box<dyn ClientOsApi> {
     }
 }
 
-pub fn get_client_os_input() -> ClientOsInputOutput {
+pub fn get_client_os_input(connection: IpcConnection) -> ClientOsInputOutput {
     let current_termios = termios::tcgetattr(0).unwrap();
     let orig_termios = Arc::new(Mutex::new(current_termios));
-    let server_buffer = SharedRingBuffer::open(ZELLIJ_IPC_PIPE).unwrap();
-    let server_sender = IpcSenderWithContext::new(server_buffer);
+    let server_sender =
+        IpcSenderWithContext::new(&connection).expect("failed to connect to server");
     ClientOsInputOutput {
         orig_termios,
         server_sender,
         client_receiver: None,
+        connection,
     }
 }
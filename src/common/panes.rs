@@ -0,0 +1,49 @@
+use nix::pty::Winsize;
+
+/// The size of a pane, or of the outer terminal, in character cells and (when the terminal
+/// reports them) pixels.
+///
+/// The pixel dimensions come from `TIOCGWINSZ`'s `ws_xpixel`/`ws_ypixel` fields and are zero on
+/// terminals that don't report them. Image protocols that run inside a pane (sixel, the kitty
+/// graphics protocol) need a per-cell pixel size to scale images correctly, which is why this
+/// carries both the cell and pixel dimensions rather than just `columns`/`rows`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PositionAndSize {
+    pub columns: u16,
+    pub rows: u16,
+    pub width_px: u16,
+    pub height_px: u16,
+}
+
+impl PositionAndSize {
+    /// The width, in pixels, of a single character cell, or `0` if the terminal didn't report
+    /// pixel dimensions.
+    pub fn cell_width_px(&self) -> u16 {
+        px_per_cell(self.width_px, self.columns)
+    }
+
+    /// The height, in pixels, of a single character cell, or `0` if the terminal didn't report
+    /// pixel dimensions.
+    pub fn cell_height_px(&self) -> u16 {
+        px_per_cell(self.height_px, self.rows)
+    }
+}
+
+fn px_per_cell(total_px: u16, cells: u16) -> u16 {
+    if cells == 0 {
+        0
+    } else {
+        total_px / cells
+    }
+}
+
+impl From<Winsize> for PositionAndSize {
+    fn from(winsize: Winsize) -> Self {
+        Self {
+            columns: winsize.ws_col,
+            rows: winsize.ws_row,
+            width_px: winsize.ws_xpixel,
+            height_px: winsize.ws_ypixel,
+        }
+    }
+}